@@ -0,0 +1,304 @@
+//! Axisymmetric duct geometry: the segment stack a particle bounces around
+//! inside, and the ray-surface intersection math used by the particle loop
+//! in `main.rs`.
+
+/// A single axisymmetric duct segment: a cylinder (`r_start == r_end`) or a
+/// truncated cone, spanning `[z_start, z_end]` with radius linearly
+/// interpolated between `r_start` and `r_end`.
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    pub z_start: f64,
+    pub z_end: f64,
+    pub r_start: f64,
+    pub r_end: f64,
+}
+
+impl Segment {
+    pub(crate) fn slope(&self) -> f64 {
+        (self.r_end - self.r_start) / (self.z_end - self.z_start)
+    }
+
+    fn radius_at(&self, z: f64) -> f64 {
+        self.r_start + self.slope() * (z - self.z_start)
+    }
+}
+
+/// An axisymmetric duct: an ordered stack of [`Segment`]s covering a
+/// contiguous `z` range, normalized so the entrance sits at `z = 0`.
+pub trait Duct {
+    fn segments(&self) -> &[Segment];
+
+    fn entrance_radius(&self) -> f64 {
+        self.segments()[0].r_start
+    }
+}
+
+/// A [`Duct`] assembled from an explicit, ordered list of segments. Build
+/// the classic two-stage screen/accel duct with [`SegmentStack::two_cylinders`],
+/// or assemble an N-stage stack (screen/accel/decel, tapered apertures, ...)
+/// with [`SegmentStack::new`].
+#[derive(Debug, Clone)]
+pub struct SegmentStack(Vec<Segment>);
+
+impl SegmentStack {
+    pub fn new(segments: Vec<Segment>) -> Self {
+        assert!(!segments.is_empty(), "a duct needs at least one segment");
+        SegmentStack(segments)
+    }
+
+    /// The two-cylinder screen/accel duct `clausing()` has always modeled,
+    /// expressed as a one-segment-per-grid stack.
+    pub fn two_cylinders(len_bottom: f64, r_bottom: f64, len_top: f64) -> Self {
+        SegmentStack::new(vec![
+            Segment {
+                z_start: 0.0,
+                z_end: len_bottom,
+                r_start: r_bottom,
+                r_end: r_bottom,
+            },
+            Segment {
+                z_start: len_bottom,
+                z_end: len_bottom + len_top,
+                r_start: 1.0,
+                r_end: 1.0,
+            },
+        ])
+    }
+}
+
+impl Duct for SegmentStack {
+    fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+}
+
+/// Outcome of flying in a straight line from `(r0, z0)` along `(vx, vy, vz)`
+/// until the ray strikes a wall or leaves the duct. Passing through an
+/// opening into a neighboring segment does not end the flight — `fly` keeps
+/// tracing the same straight line through as many segments as it takes to
+/// reach an actual wall or the duct's ends, since the ray's `(x, y)`
+/// position only has meaning relative to the single `(r0, z0)` origin it was
+/// launched from.
+pub(crate) enum FlightOutcome {
+    /// Hit the slanted/cylindrical wall of segment `segment` at `(r, z)`.
+    LateralWall { segment: usize, r: f64, z: f64 },
+    /// Hit the flat annular step between two segments of differing radius,
+    /// staying on the `segment` side of it.
+    StepWall { segment: usize, r: f64, z: f64 },
+    /// Crossed past the duct's downstream end (transmitted).
+    Escaped,
+    /// Crossed back out of the duct's upstream end (reflected away).
+    Lost,
+}
+
+/// Traces a straight-line flight within `duct`, launched from segment
+/// `segment` at radius `r0`, axial position `z0`, direction `(vx, vy, vz)`.
+/// Position along the ray is `x(t) = r0 - vx*t`, `y(t) = vy*t`,
+/// `z(t) = z0 + vz*t` (the same convention the particle loop has always
+/// used, chosen so a positive radial component moves the particle toward
+/// the axis) — this single parametrization, anchored at the original
+/// `(r0, z0)`, is carried across every segment boundary the ray passes
+/// through unobstructed, since re-centering it mid-flight would silently
+/// discard the ray's true `(x, y)` position.
+pub(crate) fn fly(
+    duct: &impl Duct,
+    segment: usize,
+    r0: f64,
+    z0: f64,
+    vx: f64,
+    vy: f64,
+    vz: f64,
+) -> FlightOutcome {
+    let segments = duct.segments();
+    let mut segment = segment;
+
+    loop {
+        let seg = segments[segment];
+
+        let z_boundary = if vz >= 0.0 { seg.z_end } else { seg.z_start };
+        let t_boundary = (z_boundary - z0) / vz;
+
+        if let Some(t) = lateral_hit(r0, z0, vx, vy, vz, seg) {
+            if t > 1e-9 && t < t_boundary {
+                let z = z0 + vz * t;
+                return FlightOutcome::LateralWall {
+                    segment,
+                    r: seg.radius_at(z),
+                    z,
+                };
+            }
+        }
+
+        let x = r0 - vx * t_boundary;
+        let y = vy * t_boundary;
+        let r_boundary = (x * x + y * y).sqrt();
+
+        if vz >= 0.0 {
+            if segment + 1 == segments.len() {
+                return FlightOutcome::Escaped;
+            }
+            let next = segments[segment + 1];
+            if r_boundary <= next.r_start {
+                segment += 1;
+            } else {
+                return FlightOutcome::StepWall {
+                    segment,
+                    r: r_boundary,
+                    z: z_boundary,
+                };
+            }
+        } else {
+            if segment == 0 {
+                return FlightOutcome::Lost;
+            }
+            let prev = segments[segment - 1];
+            if r_boundary <= prev.r_end {
+                segment -= 1;
+            } else {
+                return FlightOutcome::StepWall {
+                    segment,
+                    r: r_boundary,
+                    z: z_boundary,
+                };
+            }
+        }
+    }
+}
+
+/// Solves for the `t` at which the ray from `(r0, z0)` along `(vx, vy, vz)`
+/// leaves the interior of `seg`'s lateral surface (cylinder or cone, extended
+/// past the segment's `z` range), taking the `(linear + sqrt(disc)) / a` root.
+/// For a cylinder this is the larger root, so when `(r0, z0)` lies outside
+/// `seg`'s radius the earlier entry crossing is skipped. Returns `None` if
+/// that root is not finite and positive.
+fn lateral_hit(r0: f64, z0: f64, vx: f64, vy: f64, vz: f64, seg: Segment) -> Option<f64> {
+    let k = seg.slope();
+    let r0_seg = seg.radius_at(z0);
+    let b = k * vz;
+
+    // (r0 - vx*t)^2 + (vy*t)^2 = (r0_seg + b*t)^2, solved for t.
+    let a = vx * vx + vy * vy - b * b;
+    let linear = r0 * vx + r0_seg * b;
+    let c = r0 * r0 - r0_seg * r0_seg;
+
+    let t = if a.abs() > 1e-12 {
+        let discriminant = linear * linear - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        (linear + discriminant.sqrt()) / a
+    } else if linear.abs() > 1e-12 {
+        c / (2.0 * linear)
+    } else {
+        return None;
+    };
+
+    if t.is_finite() && t > 0.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Samples a cosine-law (Lambertian) re-emission direction off the flat
+/// annular step wall (surface normal along the duct axis), returning
+/// `(vx, vy, vz)` in the convention used by [`fly`]. `incoming_vz` is the
+/// sign of travel that produced the hit, so the emission always points back
+/// into the duct.
+pub(crate) fn emit_from_step(
+    incoming_vz: f64,
+    costheta: f64,
+    sintheta: f64,
+    phi: f64,
+) -> (f64, f64, f64) {
+    let sign = if incoming_vz >= 0.0 { -1.0 } else { 1.0 };
+    (phi.cos() * sintheta, phi.sin() * sintheta, sign * costheta)
+}
+
+/// Samples a cosine-law (Lambertian) re-emission direction off a lateral
+/// wall of slope `k` (0 for a cylinder), returning `(vx, vy, vz)` in the
+/// convention used by [`fly`].
+pub(crate) fn emit_from_lateral(k: f64, costheta: f64, sintheta: f64, phi: f64) -> (f64, f64, f64) {
+    let l = (1.0 + k * k).sqrt();
+    let vx = (costheta - k * phi.sin() * sintheta) / l;
+    let vy = phi.cos() * sintheta;
+    let vz = (k * costheta + phi.sin() * sintheta) / l;
+    (vx, vy, vz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_cylinders_matches_original_geometry() {
+        let duct = SegmentStack::two_cylinders(1.3, 2.0, 0.5);
+        assert_eq!(duct.entrance_radius(), 2.0);
+        assert_eq!(duct.segments().last().unwrap().z_end, 1.8);
+    }
+
+    #[test]
+    fn test_fly_straight_down_axis_escapes() {
+        // Passes through the segment boundary unobstructed and should keep
+        // tracing straight through to the duct's far end.
+        let duct = SegmentStack::two_cylinders(1.0, 2.0, 1.0);
+        match fly(&duct, 0, 0.0, 0.0, 0.0, 0.0, 1.0) {
+            FlightOutcome::Escaped => {}
+            _ => panic!("expected to escape out the far end"),
+        }
+    }
+
+    #[test]
+    fn test_fly_wide_offaxis_ray_hits_step() {
+        // Launch near the wide (r=2) bottom cylinder's edge, heading mostly
+        // upward: it should cross into the narrow (r=1) top segment's
+        // z-plane well outside the narrower aperture and bounce off the step.
+        let duct = SegmentStack::two_cylinders(1.0, 2.0, 1.0);
+        match fly(&duct, 0, 1.9, 0.99, 0.0, 0.0, 1.0) {
+            FlightOutcome::StepWall { segment, .. } => assert_eq!(segment, 0),
+            _ => panic!("expected to hit the annular step wall"),
+        }
+    }
+
+    #[test]
+    fn test_fly_passes_through_aperture_into_far_wall() {
+        // Launched near the axis, angled outward enough to clear the
+        // aperture into the top cylinder but still hit its lateral wall
+        // afterward: exercises carrying the original (r0, z0) anchor across
+        // a segment boundary instead of re-centering on it.
+        let duct = SegmentStack::two_cylinders(1.0, 2.0, 1.0);
+        match fly(&duct, 0, 0.3, 0.0, -0.4, 0.0, 0.9) {
+            FlightOutcome::LateralWall { segment, .. } => assert_eq!(segment, 1),
+            _ => panic!("expected to hit the top cylinder's lateral wall"),
+        }
+    }
+
+    #[test]
+    fn test_two_cylinder_clausing_factor_matches_recorded_baseline() {
+        // Numeric regression guard for the two-cylinder case `clausing()`
+        // wraps `SegmentStack::two_cylinders` around: these exact
+        // parameters/seed produced `clausing_factor ~= 0.76` on the
+        // pre-multi-segment implementation. A geometry-engine change that
+        // silently alters the simulated physics for this unchanged case
+        // (e.g. losing the true (x, y) position across a segment boundary)
+        // pushes this well outside tolerance.
+        let results = crate::clausing(crate::ClausingParams {
+            thick_screen: 1.0,
+            thick_accel: 0.5,
+            r_screen: 2.0,
+            r_accel: 1.0,
+            grid_space: 0.3,
+            npart: 5000,
+            jackknife_blocks: 20,
+            roulette_threshold: 1000,
+            roulette_kill_prob: 0.1,
+            seed: Some(99),
+        });
+
+        assert!(
+            (results.clausing_factor - 0.7616).abs() < 0.05,
+            "clausing_factor {} drifted outside tolerance of the recorded baseline 0.7616",
+            results.clausing_factor
+        );
+    }
+}
@@ -1,8 +1,21 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
 use std::f64::consts::PI;
 
+use geometry::{emit_from_lateral, emit_from_step, fly, Duct, FlightOutcome, SegmentStack};
+
+mod geometry;
+mod optimize;
+
+/// Hard ceiling on bounces per history, well above any sane `roulette_threshold`.
+/// The Russian-roulette kill is only probabilistic, so a misconfigured
+/// `roulette_kill_prob` (e.g. 0.0) would otherwise let a history run forever;
+/// hitting this ceiling is treated the same as a roulette kill.
+const MAX_BOUNCES: usize = 1_000_000;
+
 /// Structure to hold input parameters for the Clausing factor calculation
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct ClausingParams {
     pub thick_screen: f64,
     pub thick_accel: f64,
@@ -10,196 +23,299 @@ pub struct ClausingParams {
     pub r_accel: f64,
     pub grid_space: f64,
     pub npart: usize,
+    pub jackknife_blocks: usize,   // Number of delete-one-block jackknife blocks
+    pub roulette_threshold: usize, // Bounce count past which Russian roulette kicks in
+    pub roulette_kill_prob: f64,   // Per-bounce kill probability once past roulette_threshold
+    pub seed: Option<u64>,         // RNG seed; None draws a fresh nondeterministic seed
 }
 
 /// Structure to hold the results of the Clausing calculation
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct ClausingResults {
     pub clausing_factor: f64,
+    pub clausing_factor_err: f64, // Jackknife standard error on clausing_factor
     pub max_count: usize,
-    pub nlost: usize,
-    pub den_cor: f64, // Downstream correction factor
+    pub nlost: usize, // Diagnostic count of Russian-roulette terminations
+    pub den_cor: f64, // Downstream correction factor (jackknife bias-corrected)
+    pub den_cor_err: f64, // Jackknife standard error on den_cor
 }
 
-/// Monte Carlo routine that calculates Clausing factor for CEX
-/// Returns Clausing Factor and Downstream Correction factor
-pub fn clausing(params: ClausingParams) -> ClausingResults {
-    let mut rng = rand::rng();
+/// Per-worker accumulator for a batch of particle histories, reduced across
+/// workers once all batches have finished.
+struct BatchStats {
+    block_escape: Vec<f64>,
+    block_npart: Vec<usize>,
+    block_vz0tot: Vec<f64>,
+    block_vztot: Vec<f64>,
+    max_count: usize,
+    nlost: usize,
+}
 
-    // Calculate normalized dimensions (assumes rTop = 1)
-    let r_bottom = params.r_screen / params.r_accel;
-    let len_bottom = (params.thick_screen + params.grid_space) / params.r_accel;
-    let len_top = params.thick_accel / params.r_accel;
-    let length = len_top + len_bottom;
+impl BatchStats {
+    fn new(num_blocks: usize) -> Self {
+        BatchStats {
+            block_escape: vec![0.0_f64; num_blocks],
+            block_npart: vec![0_usize; num_blocks],
+            block_vz0tot: vec![0.0_f64; num_blocks],
+            block_vztot: vec![0.0_f64; num_blocks],
+            max_count: 0,
+            nlost: 0,
+        }
+    }
 
-    let mut iescape = 0;
-    let mut max_count = 0;
-    let mut nlost = 0;
-    let mut vztot = 0.0;
-    let mut vz0tot = 0.0;
+    fn reduce(batches: Vec<BatchStats>, num_blocks: usize) -> Self {
+        let mut total = BatchStats::new(num_blocks);
+        for batch in batches {
+            for i in 0..num_blocks {
+                total.block_escape[i] += batch.block_escape[i];
+                total.block_npart[i] += batch.block_npart[i];
+                total.block_vz0tot[i] += batch.block_vz0tot[i];
+                total.block_vztot[i] += batch.block_vztot[i];
+            }
+            total.max_count = total.max_count.max(batch.max_count);
+            total.nlost += batch.nlost;
+        }
+        total
+    }
+}
 
-    // Main particle loop
-    for _ipart in 0..params.npart {
-        // Launch from bottom
-        let mut r0 = r_bottom * rng.random::<f64>().sqrt();
-        let mut z0 = 0.0;
+/// Simulates particles `ipart_start..ipart_end` (out of `npart` total, which
+/// determines jackknife block membership) flying through `duct`, using
+/// `rng`, and accumulates the results into a `BatchStats`.
+#[allow(clippy::too_many_arguments)]
+fn simulate_batch(
+    ipart_start: usize,
+    ipart_end: usize,
+    npart: usize,
+    num_blocks: usize,
+    duct: &impl Duct,
+    roulette_threshold: usize,
+    roulette_kill_prob: f64,
+    rng: &mut impl Rng,
+) -> BatchStats {
+    let mut stats = BatchStats::new(num_blocks);
+    let entrance_radius = duct.entrance_radius();
+
+    for ipart in ipart_start..ipart_end {
+        let block = ipart * num_blocks / npart;
+        stats.block_npart[block] += 1;
+
+        // Launch from the entrance face
+        let mut r = entrance_radius * rng.random::<f64>().sqrt();
+        let mut z = 0.0;
+        let mut segment = 0;
 
         let mut costheta = (1.0 - rng.random::<f64>()).sqrt();
         if costheta > 0.99999 {
             costheta = 0.99999;
         }
-
         let phi = 2.0 * PI * rng.random::<f64>();
         let sintheta = (1.0 - costheta.powi(2)).sqrt();
+        let (mut vx, mut vy, mut vz) = (phi.cos() * sintheta, phi.sin() * sintheta, costheta);
 
-        let mut vx = phi.cos() * sintheta;
-        let mut vy = phi.sin() * sintheta;
-        let mut vz = costheta;
-
-        let mut rf = r_bottom;
-        let mut t = (vx * r0 + ((vx.powi(2) + vy.powi(2)) * rf.powi(2) - (vy * r0).powi(2)).sqrt())
-            / (vx.powi(2) + vy.powi(2));
-        let mut z = z0 + vz * t;
-
-        vz0tot += vz;
+        stats.block_vz0tot[block] += vz;
 
         let mut icount = 0;
-        let mut notgone = true;
-
-        while notgone {
-            icount += 1;
-
-            // Hit wall of bottom cylinder and is re-emitted
-            if z < len_bottom {
-                r0 = r_bottom;
-                z0 = z;
-
-                costheta = (1.0 - rng.random::<f64>()).sqrt();
-                if costheta > 0.99999 {
-                    costheta = 0.99999;
+        let mut w = 1.0;
+
+        loop {
+            match fly(duct, segment, r, z, vx, vy, vz) {
+                FlightOutcome::Escaped => {
+                    stats.block_escape[block] += w;
+                    stats.block_vztot[block] += w * vz;
+                    break;
                 }
-
-                let phi = 2.0 * PI * rng.random::<f64>();
-                let sintheta = (1.0 - costheta.powi(2)).sqrt();
-
-                vz = phi.cos() * sintheta;
-                vy = phi.sin() * sintheta;
-                vx = costheta;
-
-                rf = r_bottom;
-                t = (vx * r0 + ((vx.powi(2) + vy.powi(2)) * rf.powi(2) - (vy * r0).powi(2)).sqrt())
-                    / (vx.powi(2) + vy.powi(2));
-                z = z0 + t * vz;
-            }
-
-            // Emitted below but going up
-            if z >= len_bottom && z0 < len_bottom {
-                // Find radius at len_bottom
-                t = (len_bottom - z0) / vz;
-                let r = ((r0 - vx * t).powi(2) + (vy * t).powi(2)).sqrt();
-
-                if r <= 1.0 {
-                    // Continuing upward
-                    rf = 1.0;
-                    t = (vx * r0
-                        + ((vx.powi(2) + vy.powi(2)) * rf.powi(2) - (vy * r0).powi(2)).sqrt())
-                        / (vx.powi(2) + vy.powi(2));
-                    z = z0 + vz * t;
-                } else {
-                    // Hit the upstream side of the accel grid and is re-emitted downward
-                    r0 = r;
-                    z0 = len_bottom;
-
-                    costheta = (1.0 - rng.random::<f64>()).sqrt();
-                    if costheta > 0.99999 {
-                        costheta = 0.99999;
+                FlightOutcome::Lost => break,
+                FlightOutcome::LateralWall {
+                    segment: hit_segment,
+                    r: hit_r,
+                    z: hit_z,
+                } => {
+                    icount += 1;
+                    if icount >= MAX_BOUNCES
+                        || roulette(&mut w, icount, roulette_threshold, roulette_kill_prob, rng)
+                    {
+                        stats.nlost += 1;
+                        break;
                     }
 
-                    let phi = 2.0 * PI * rng.random::<f64>();
-                    let sintheta = (1.0 - costheta.powi(2)).sqrt();
-
-                    vx = phi.cos() * sintheta;
-                    vy = phi.sin() * sintheta;
-                    vz = -costheta;
-
-                    rf = r_bottom;
-                    t = (vx * r0
-                        + ((vx.powi(2) + vy.powi(2)) * rf.powi(2) - (vy * r0).powi(2)).sqrt())
-                        / (vx.powi(2) + vy.powi(2));
-                    z = z0 + vz * t;
-                }
-            }
-
-            // Hit the upper cylinder wall and is re-emitted
-            if z >= len_bottom && z <= length {
-                r0 = 1.0;
-                z0 = z;
-
-                costheta = (1.0 - rng.random::<f64>()).sqrt();
-                if costheta > 0.99999 {
-                    costheta = 0.99999;
+                    let (costheta, sintheta, phi) = sample_diffuse_angles(rng);
+                    let k = duct.segments()[hit_segment].slope();
+                    let (new_vx, new_vy, new_vz) = emit_from_lateral(k, costheta, sintheta, phi);
+                    segment = hit_segment;
+                    r = hit_r;
+                    z = hit_z;
+                    vx = new_vx;
+                    vy = new_vy;
+                    vz = new_vz;
                 }
-
-                let phi = 2.0 * PI * rng.random::<f64>();
-                let sintheta = (1.0 - costheta.powi(2)).sqrt();
-
-                vz = phi.cos() * sintheta;
-                vy = phi.sin() * sintheta;
-                vx = costheta;
-
-                rf = 1.0;
-                t = (vx * r0 + ((vx.powi(2) + vy.powi(2)) * rf.powi(2) - (vy * r0).powi(2)).sqrt())
-                    / (vx.powi(2) + vy.powi(2));
-                z = z0 + t * vz;
-
-                // Find z when particle hits the bottom cylinder
-                if z < len_bottom {
-                    rf = r_bottom;
-                    let discriminant = (vx.powi(2) + vy.powi(2)) * rf.powi(2) - (vy * r0).powi(2);
-
-                    if discriminant < 0.0 {
-                        // If sqrt argument is less than 0 then set sqrt term to 0
-                        t = (vx * r0) / (vx.powi(2) + vy.powi(2));
-                    } else {
-                        t = (vx * r0 + discriminant.sqrt()) / (vx.powi(2) + vy.powi(2));
+                FlightOutcome::StepWall {
+                    segment: hit_segment,
+                    r: hit_r,
+                    z: hit_z,
+                } => {
+                    icount += 1;
+                    if icount >= MAX_BOUNCES
+                        || roulette(&mut w, icount, roulette_threshold, roulette_kill_prob, rng)
+                    {
+                        stats.nlost += 1;
+                        break;
                     }
-                    z = z0 + vz * t;
+
+                    let (costheta, sintheta, phi) = sample_diffuse_angles(rng);
+                    let (new_vx, new_vy, new_vz) = emit_from_step(vz, costheta, sintheta, phi);
+                    segment = hit_segment;
+                    r = hit_r;
+                    z = hit_z;
+                    vx = new_vx;
+                    vy = new_vy;
+                    vz = new_vz;
                 }
             }
 
-            // Check exit conditions
-            if z < 0.0 {
-                notgone = false;
+            if icount > stats.max_count {
+                stats.max_count = icount;
             }
+        }
+    }
 
-            if z > length {
-                iescape += 1;
-                vztot += vz;
-                notgone = false;
-            }
+    stats
+}
 
-            if icount > 1000 {
-                notgone = false;
-                nlost += 1;
-            }
+/// Samples a cosine-law (Lambertian) emission angle: `costheta` in `[0, 1)`,
+/// its matching `sintheta`, and an azimuth `phi` uniform on `[0, 2*PI)`.
+fn sample_diffuse_angles(rng: &mut impl Rng) -> (f64, f64, f64) {
+    let mut costheta = (1.0 - rng.random::<f64>()).sqrt();
+    if costheta > 0.99999 {
+        costheta = 0.99999;
+    }
+    let phi = 2.0 * PI * rng.random::<f64>();
+    let sintheta = (1.0 - costheta.powi(2)).sqrt();
+    (costheta, sintheta, phi)
+}
 
-            if icount > max_count {
-                max_count = icount;
-            }
-        }
+/// Past `roulette_threshold` bounces, kills the history with probability
+/// `roulette_kill_prob`; survivors have `w` reweighted so the expectation of
+/// the escape/moment tallies is unchanged. Returns `true` if killed.
+fn roulette(
+    w: &mut f64,
+    icount: usize,
+    roulette_threshold: usize,
+    roulette_kill_prob: f64,
+    rng: &mut impl Rng,
+) -> bool {
+    if icount <= roulette_threshold {
+        return false;
     }
+    if rng.random::<f64>() < roulette_kill_prob {
+        return true;
+    }
+    *w /= 1.0 - roulette_kill_prob;
+    false
+}
+
+/// Monte Carlo routine that calculates Clausing factor for CEX
+/// Returns Clausing Factor and Downstream Correction factor
+pub fn clausing(params: ClausingParams) -> ClausingResults {
+    // Calculate normalized dimensions (assumes rTop = 1)
+    let r_bottom = params.r_screen / params.r_accel;
+    let len_bottom = (params.thick_screen + params.grid_space) / params.r_accel;
+    let len_top = params.thick_accel / params.r_accel;
+    let duct = SegmentStack::two_cylinders(len_bottom, r_bottom, len_top);
+
+    let num_blocks = params.jackknife_blocks.max(1);
+
+    // Split the particle histories across rayon worker threads. Each worker
+    // gets its own ChaCha8Rng stream derived from the same base seed, so the
+    // aggregate result is bit-reproducible for a given seed and thread count.
+    let base_seed = params.seed.unwrap_or_else(|| rand::rng().random());
+    let num_workers = rayon::current_num_threads().min(params.npart.max(1));
+    let chunk = params.npart.div_ceil(num_workers);
+
+    let batches: Vec<BatchStats> = (0..num_workers)
+        .into_par_iter()
+        .map(|worker| {
+            let ipart_start = (worker * chunk).min(params.npart);
+            let ipart_end = (ipart_start + chunk).min(params.npart);
+
+            let mut rng = ChaCha8Rng::seed_from_u64(base_seed);
+            rng.set_stream(worker as u64);
+
+            simulate_batch(
+                ipart_start,
+                ipart_end,
+                params.npart,
+                num_blocks,
+                &duct,
+                params.roulette_threshold,
+                params.roulette_kill_prob,
+                &mut rng,
+            )
+        })
+        .collect();
+
+    let BatchStats {
+        block_escape,
+        block_npart,
+        block_vz0tot,
+        block_vztot,
+        max_count,
+        nlost,
+    } = BatchStats::reduce(batches, num_blocks);
 
     // Calculate results
-    let clausing_factor = (r_bottom.powi(2) * iescape as f64) / params.npart as f64;
+    let iescape: f64 = block_escape.iter().sum();
+    let vz0tot: f64 = block_vz0tot.iter().sum();
+    let vztot: f64 = block_vztot.iter().sum();
+
+    let clausing_factor = (r_bottom.powi(2) * iescape) / params.npart as f64;
     let vz0av = vz0tot / params.npart as f64;
-    let vzav = vztot / iescape as f64;
-    let den_cor = vz0av / vzav; // Downstream correction factor
+    let vzav = vztot / iescape;
+    let den_cor_raw = vz0av / vzav; // Downstream correction factor, naive ratio-of-means
+
+    // Delete-one-block jackknife: each leave-one-out replicate recomputes the
+    // full estimator from the remaining blocks' accumulated sums.
+    let mut factor_loo = Vec::with_capacity(num_blocks);
+    let mut den_cor_loo = Vec::with_capacity(num_blocks);
+    for i in 0..num_blocks {
+        let npart_i = (params.npart - block_npart[i]) as f64;
+        let escape_i = iescape - block_escape[i];
+        let vz0_i = vz0tot - block_vz0tot[i];
+        let vz_i = vztot - block_vztot[i];
+
+        factor_loo.push(r_bottom.powi(2) * escape_i / npart_i);
+
+        // If block i holds every escaping particle, the leave-one-out escape
+        // count is zero and the ratio is 0.0/0.0 = NaN; drop that replicate
+        // rather than let it poison the mean/error/bias correction below.
+        if escape_i > 0.0 {
+            den_cor_loo.push((vz0_i / npart_i) / (vz_i / escape_i));
+        }
+    }
+
+    let b = num_blocks as f64;
+    let factor_mean = factor_loo.iter().sum::<f64>() / b;
+    let clausing_factor_err = ((b - 1.0) / b
+        * factor_loo.iter().map(|t| (t - factor_mean).powi(2)).sum::<f64>())
+    .sqrt();
+
+    let b_den = den_cor_loo.len().max(1) as f64;
+    let den_cor_mean = den_cor_loo.iter().sum::<f64>() / b_den;
+    let den_cor_err = ((b_den - 1.0) / b_den
+        * den_cor_loo.iter().map(|t| (t - den_cor_mean).powi(2)).sum::<f64>())
+    .sqrt();
+
+    // Standard jackknife bias correction for the ratio-of-means estimator,
+    // over whichever replicates were valid.
+    let den_cor = b_den * den_cor_raw - (b_den - 1.0) * den_cor_mean;
 
     ClausingResults {
         clausing_factor,
+        clausing_factor_err,
         max_count,
         nlost,
         den_cor,
+        den_cor_err,
     }
 }
 
@@ -212,6 +328,10 @@ fn main() {
         r_accel: 1.0,
         grid_space: 0.3,
         npart: 10000,
+        jackknife_blocks: 20,
+        roulette_threshold: 1000,
+        roulette_kill_prob: 0.1,
+        seed: Some(42),
     };
 
     println!("Running Clausing factor calculation...");
@@ -221,10 +341,49 @@ fn main() {
     let results = clausing(params);
 
     println!("Results:");
-    println!("  Clausing Factor: {:.6}", results.clausing_factor);
+    println!(
+        "  Clausing Factor: {:.6} +/- {:.6}",
+        results.clausing_factor, results.clausing_factor_err
+    );
     println!("  Max Count: {}", results.max_count);
     println!("  Particles Lost: {}", results.nlost);
-    println!("  Downstream Correction Factor: {:.6}", results.den_cor);
+    println!(
+        "  Downstream Correction Factor: {:.6} +/- {:.6}",
+        results.den_cor, results.den_cor_err
+    );
+    println!();
+
+    // Example inverse-design usage: search thick_screen/r_screen for a
+    // geometry that hits a target Clausing factor.
+    let optimize_result = optimize::optimize(optimize::OptimizeParams {
+        base: params,
+        bounds: optimize::GeometryBounds {
+            thick_screen: Some((0.2, 3.0)),
+            thick_accel: None,
+            r_screen: Some((0.5, 3.0)),
+            r_accel: None,
+            grid_space: None,
+        },
+        target: 0.5,
+        population_size: 16,
+        elite_fraction: 0.25,
+        generations: 8,
+        initial_step_frac: 0.3,
+        step_decay: 0.8,
+        npart_start: 500,
+        npart_end: 5000,
+        seed: Some(42),
+    });
+
+    println!("Running inverse geometry design (target Clausing factor = 0.5)...");
+    println!(
+        "  Best thick_screen: {:.6}, r_screen: {:.6}",
+        optimize_result.params.thick_screen, optimize_result.params.r_screen
+    );
+    println!(
+        "  Achieved Clausing Factor: {:.6} +/- {:.6}",
+        optimize_result.clausing_factor, optimize_result.clausing_factor_err
+    );
 }
 
 #[cfg(test)]
@@ -240,6 +399,10 @@ mod tests {
             r_accel: 1.0,
             grid_space: 0.3,
             npart: 1000,
+            jackknife_blocks: 20,
+            roulette_threshold: 1000,
+            roulette_kill_prob: 0.1,
+            seed: Some(1),
         };
 
         let results = clausing(params);
@@ -248,5 +411,54 @@ mod tests {
         assert!(results.clausing_factor > 0.0);
         assert!(results.clausing_factor <= 4.0); // r_bottom^2 = 4.0 is theoretical max
         assert!(results.den_cor > 0.0);
+        assert!(results.clausing_factor_err >= 0.0);
+        assert!(results.den_cor_err >= 0.0);
+    }
+
+    #[test]
+    fn test_clausing_seed_is_reproducible() {
+        let make_params = || ClausingParams {
+            thick_screen: 1.0,
+            thick_accel: 0.5,
+            r_screen: 2.0,
+            r_accel: 1.0,
+            grid_space: 0.3,
+            npart: 1000,
+            jackknife_blocks: 20,
+            roulette_threshold: 1000,
+            roulette_kill_prob: 0.1,
+            seed: Some(7),
+        };
+
+        let a = clausing(make_params());
+        let b = clausing(make_params());
+
+        assert_eq!(a.clausing_factor, b.clausing_factor);
+        assert_eq!(a.den_cor, b.den_cor);
+    }
+
+    #[test]
+    fn test_den_cor_finite_when_a_block_holds_all_escapes() {
+        // Small npart, low-transmission geometry: only a handful of particles
+        // escape, the regime in which every escaping particle can land in a
+        // single jackknife block, which used to make that leave-one-out
+        // replicate's den_cor NaN.
+        let params = ClausingParams {
+            thick_screen: 5.0,
+            thick_accel: 5.0,
+            r_screen: 0.3,
+            r_accel: 1.0,
+            grid_space: 0.3,
+            npart: 50,
+            jackknife_blocks: 20,
+            roulette_threshold: 1000,
+            roulette_kill_prob: 0.1,
+            seed: Some(25),
+        };
+
+        let results = clausing(params);
+
+        assert!(results.den_cor.is_finite());
+        assert!(results.den_cor_err.is_finite());
     }
 }
@@ -0,0 +1,228 @@
+use crate::{clausing, ClausingParams, ClausingResults};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Normal};
+
+/// Per-field search bounds for [`optimize`]. `None` fixes the field at its
+/// value in [`OptimizeParams::base`]; `Some((min, max))` makes it a free
+/// dimension searched within that inclusive range.
+#[derive(Debug, Clone, Copy)]
+pub struct GeometryBounds {
+    pub thick_screen: Option<(f64, f64)>,
+    pub thick_accel: Option<(f64, f64)>,
+    pub r_screen: Option<(f64, f64)>,
+    pub r_accel: Option<(f64, f64)>,
+    pub grid_space: Option<(f64, f64)>,
+}
+
+/// Input to the inverse geometry search: find grid dimensions that make
+/// `clausing()` hit a target transmission factor.
+#[derive(Debug)]
+pub struct OptimizeParams {
+    pub base: ClausingParams, // Non-geometry settings and fallback values for fixed fields
+    pub bounds: GeometryBounds,
+    pub target: f64,
+    pub population_size: usize,
+    pub elite_fraction: f64,
+    pub generations: usize,
+    pub initial_step_frac: f64, // Initial mutation std, as a fraction of each free field's range
+    pub step_decay: f64,        // Per-generation multiplicative shrink of the step size
+    pub npart_start: usize,     // npart used to evaluate early (noisy, cheap) generations
+    pub npart_end: usize,       // npart used to evaluate the final (precise) generation
+    pub seed: Option<u64>,
+}
+
+/// Best geometry found by [`optimize`] and the transmission factor it achieved.
+#[derive(Debug)]
+pub struct OptimizeResult {
+    pub params: ClausingParams,
+    pub clausing_factor: f64,
+    pub clausing_factor_err: f64,
+}
+
+/// Evolution-strategy search over `ClausingParams` geometry fields that
+/// minimizes `(clausing_factor - target)^2`. Maintains a population of
+/// candidate geometries, keeps the top `elite_fraction` as parents each
+/// generation, and produces children by Gaussian mutation of the free
+/// dimensions with a step size that shrinks by `step_decay` every
+/// generation. `npart` is ramped from `npart_start` to `npart_end` across
+/// generations so Monte Carlo noise doesn't dominate selection once the
+/// population has converged.
+pub fn optimize(params: OptimizeParams) -> OptimizeResult {
+    let mut rng = ChaCha8Rng::seed_from_u64(params.seed.unwrap_or_else(|| rand::rng().random()));
+
+    let elite_count = ((params.population_size as f64 * params.elite_fraction).round() as usize)
+        .clamp(1, params.population_size);
+
+    let mut population: Vec<ClausingParams> = (0..params.population_size)
+        .map(|_| random_candidate(&params.base, &params.bounds, &mut rng))
+        .collect();
+
+    let mut best: Option<(ClausingParams, ClausingResults)> = None;
+
+    for gen in 0..params.generations {
+        let npart = npart_for_generation(
+            gen,
+            params.generations,
+            params.npart_start,
+            params.npart_end,
+        );
+        let step_frac = params.initial_step_frac * params.step_decay.powi(gen as i32);
+
+        let mut evaluated: Vec<(ClausingParams, ClausingResults)> = population
+            .iter()
+            .map(|candidate| {
+                let trial = ClausingParams {
+                    npart,
+                    ..*candidate
+                };
+                (*candidate, clausing(trial))
+            })
+            .collect();
+
+        evaluated.sort_by(|(_, a), (_, b)| {
+            let loss_a = (a.clausing_factor - params.target).powi(2);
+            let loss_b = (b.clausing_factor - params.target).powi(2);
+            loss_a.total_cmp(&loss_b)
+        });
+
+        let generation_best = evaluated[0];
+        let generation_best_loss = (generation_best.1.clausing_factor - params.target).powi(2);
+        let is_better = best.is_none_or(|(_, results)| {
+            generation_best_loss < (results.clausing_factor - params.target).powi(2)
+        });
+        if is_better {
+            best = Some(generation_best);
+        }
+
+        let elites: Vec<ClausingParams> =
+            evaluated[..elite_count].iter().map(|(c, _)| *c).collect();
+
+        population = (0..params.population_size)
+            .map(|i| {
+                if i < elite_count {
+                    elites[i]
+                } else {
+                    let parent = elites[rng.random_range(0..elite_count)];
+                    mutate(parent, &params.bounds, step_frac, &mut rng)
+                }
+            })
+            .collect();
+    }
+
+    let (best_params, best_results) =
+        best.expect("optimize requires at least one generation to produce a result");
+
+    OptimizeResult {
+        params: best_params,
+        clausing_factor: best_results.clausing_factor,
+        clausing_factor_err: best_results.clausing_factor_err,
+    }
+}
+
+/// Linearly ramps `npart` from `start` at generation 0 to `end` at the final
+/// generation.
+fn npart_for_generation(gen: usize, generations: usize, start: usize, end: usize) -> usize {
+    if generations <= 1 {
+        return end;
+    }
+    let frac = gen as f64 / (generations - 1) as f64;
+    (start as f64 + frac * (end as f64 - start as f64)).round() as usize
+}
+
+/// Builds a candidate by sampling each free field uniformly within its
+/// bounds; fixed fields are left at their `base` value.
+fn random_candidate(
+    base: &ClausingParams,
+    bounds: &GeometryBounds,
+    rng: &mut impl Rng,
+) -> ClausingParams {
+    let mut candidate = *base;
+    if let Some((lo, hi)) = bounds.thick_screen {
+        candidate.thick_screen = rng.random_range(lo..=hi);
+    }
+    if let Some((lo, hi)) = bounds.thick_accel {
+        candidate.thick_accel = rng.random_range(lo..=hi);
+    }
+    if let Some((lo, hi)) = bounds.r_screen {
+        candidate.r_screen = rng.random_range(lo..=hi);
+    }
+    if let Some((lo, hi)) = bounds.r_accel {
+        candidate.r_accel = rng.random_range(lo..=hi);
+    }
+    if let Some((lo, hi)) = bounds.grid_space {
+        candidate.grid_space = rng.random_range(lo..=hi);
+    }
+    candidate
+}
+
+/// Produces a child by perturbing each free field of `parent` with Gaussian
+/// noise of standard deviation `step_frac * (max - min)`, clamped back into
+/// bounds; fixed fields are left untouched.
+fn mutate(
+    parent: ClausingParams,
+    bounds: &GeometryBounds,
+    step_frac: f64,
+    rng: &mut impl Rng,
+) -> ClausingParams {
+    let mut child = parent;
+    mutate_field(&mut child.thick_screen, bounds.thick_screen, step_frac, rng);
+    mutate_field(&mut child.thick_accel, bounds.thick_accel, step_frac, rng);
+    mutate_field(&mut child.r_screen, bounds.r_screen, step_frac, rng);
+    mutate_field(&mut child.r_accel, bounds.r_accel, step_frac, rng);
+    mutate_field(&mut child.grid_space, bounds.grid_space, step_frac, rng);
+    child
+}
+
+fn mutate_field(value: &mut f64, bounds: Option<(f64, f64)>, step_frac: f64, rng: &mut impl Rng) {
+    if let Some((lo, hi)) = bounds {
+        let std = (step_frac * (hi - lo)).max(f64::EPSILON);
+        let noise = Normal::new(0.0, std).unwrap().sample(rng);
+        *value = (*value + noise).clamp(lo, hi);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimize_hits_target_within_bounds() {
+        let base = ClausingParams {
+            thick_screen: 1.0,
+            thick_accel: 0.5,
+            r_screen: 2.0,
+            r_accel: 1.0,
+            grid_space: 0.3,
+            npart: 0, // overwritten per generation
+            jackknife_blocks: 20,
+            roulette_threshold: 1000,
+            roulette_kill_prob: 0.1,
+            seed: Some(3),
+        };
+
+        let result = optimize(OptimizeParams {
+            base,
+            bounds: GeometryBounds {
+                thick_screen: Some((0.2, 3.0)),
+                thick_accel: None,
+                r_screen: Some((0.5, 3.0)),
+                r_accel: None,
+                grid_space: None,
+            },
+            target: 0.5,
+            population_size: 8,
+            elite_fraction: 0.25,
+            generations: 4,
+            initial_step_frac: 0.3,
+            step_decay: 0.6,
+            npart_start: 200,
+            npart_end: 800,
+            seed: Some(11),
+        });
+
+        assert!(result.params.thick_screen >= 0.2 && result.params.thick_screen <= 3.0);
+        assert!(result.params.r_screen >= 0.5 && result.params.r_screen <= 3.0);
+        assert!(result.clausing_factor > 0.0);
+    }
+}